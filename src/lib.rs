@@ -1,51 +1,211 @@
 use std::fs;
+use std::path::Path;
 use zed_extension_api::{
-    self as zed, download_file, latest_github_release,
+    self as zed, download_file, github_release_by_tag_name, latest_github_release,
     lsp::{Completion, CompletionKind},
-    register_extension, set_language_server_installation_status, CodeLabel, CodeLabelSpan,
-    DownloadedFileType, Extension, GithubReleaseOptions, LanguageServerId,
-    LanguageServerInstallationStatus, Worktree,
+    make_file_executable, register_extension, serde_json,
+    settings::LspSettings,
+    set_language_server_installation_status, CodeLabel, CodeLabelSpan, DownloadedFileType,
+    Extension, GithubReleaseOptions, LanguageServerId, LanguageServerInstallationStatus, Worktree,
 };
 
+const SERVER_NAME: &str = "nextflow";
+
 struct NextflowExtension {
     cached_jar_path: Option<String>,
 }
 
+impl NextflowExtension {
+    /// Locate a usable `java` launcher.
+    ///
+    /// Probes, in order: the user-configured `java_path` setting, `$JAVA_HOME/bin/java`,
+    /// a `java` on `$PATH` (via the worktree), and finally the JRE bundled inside the
+    /// extension directory. The bundled path is only a last resort so the server still
+    /// launches on machines without a system Java.
+    fn java_binary(&self, worktree: &Worktree, configured: Option<&str>) -> String {
+        if let Some(path) = configured {
+            if !path.is_empty() {
+                return path.to_string();
+            }
+        }
+
+        if let Some((_, java_home)) = worktree
+            .shell_env()
+            .into_iter()
+            .find(|(key, _)| key == "JAVA_HOME")
+        {
+            if !java_home.is_empty() {
+                return format!("{java_home}/bin/java");
+            }
+        }
+
+        if let Some(java) = worktree.which("java") {
+            return java;
+        }
+
+        // Bundled JRE shipped inside the extension directory.
+        "./bin/java".to_string()
+    }
+
+    /// Translate the user's Zed settings block for this server into the configuration
+    /// object the Nextflow language server expects (the same shape as its VS Code
+    /// `nextflow.*` settings). Unset keys are omitted so the server keeps its defaults.
+    fn server_configuration(&self, worktree: &Worktree) -> serde_json::Value {
+        let settings = LspSettings::for_worktree(SERVER_NAME, worktree)
+            .ok()
+            .and_then(|s| s.settings)
+            .unwrap_or_default();
+
+        let mut config = serde_json::Map::new();
+
+        let mut files = serde_json::Map::new();
+        if let Some(include) = settings.get("files").and_then(|f| f.get("include")) {
+            files.insert("include".into(), include.clone());
+        }
+        if let Some(exclude) = settings.get("files").and_then(|f| f.get("exclude")) {
+            files.insert("exclude".into(), exclude.clone());
+        }
+        if !files.is_empty() {
+            config.insert("files".into(), serde_json::Value::Object(files));
+        }
+
+        if let Some(harshil) = settings
+            .get("formatting")
+            .and_then(|f| f.get("harshil_alignment"))
+            .and_then(|v| v.as_bool())
+        {
+            config.insert(
+                "formatting".into(),
+                serde_json::json!({ "harshilAlignment": harshil }),
+            );
+        }
+
+        if let Some(mode) = settings
+            .get("error_reporting_mode")
+            .and_then(|v| v.as_str())
+        {
+            config.insert("errorReportingMode".into(), mode.into());
+        }
+
+        // `java_home` is a *directory* (the server sets the compiler's JAVA_HOME from
+        // it), distinct from the `java_path` launcher executable used to spawn the JVM.
+        if let Some(java_home) = settings.get("java_home").and_then(|v| v.as_str()) {
+            if !java_home.is_empty() {
+                config.insert("java".into(), serde_json::json!({ "home": java_home }));
+            }
+        }
+
+        serde_json::json!({ "nextflow": serde_json::Value::Object(config) })
+    }
+}
+
 impl NextflowExtension {
     fn language_server_jar_path(
         &mut self,
         language_server_id: &LanguageServerId,
+        worktree: &Worktree,
     ) -> zed::Result<String> {
         let jar_path = "language-server-all.jar".to_string();
+        let version_path = "language-server-all.version";
+
+        let settings = LspSettings::for_worktree(SERVER_NAME, worktree)
+            .ok()
+            .and_then(|s| s.settings);
+        let settings = settings.as_ref();
+
+        // A locally provided jar pins the source to a file on disk; resolve it through
+        // the worktree so teams can ship a reproducible server with their pipeline repo.
+        if let Some(local) = settings
+            .and_then(|s| s.get("jar_path"))
+            .and_then(|v| v.as_str())
+            .filter(|p| !p.is_empty())
+        {
+            let path = Path::new(local);
+            let resolved = if path.is_absolute() {
+                local.to_string()
+            } else {
+                Path::new(&worktree.root_path())
+                    .join(path)
+                    .to_string_lossy()
+                    .into_owned()
+            };
+            self.cached_jar_path = Some(resolved.clone());
+            return Ok(resolved);
+        }
+
+        let asset_name = settings
+            .and_then(|s| s.get("asset_name"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("language-server-all.jar")
+            .to_string();
+        let release_tag = settings
+            .and_then(|s| s.get("release_tag"))
+            .and_then(|v| v.as_str())
+            .filter(|t| !t.is_empty())
+            .map(|t| t.to_string());
+        let pre_release = settings
+            .and_then(|s| s.get("pre_release"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
 
         if let Some(path) = &self.cached_jar_path {
             if fs::metadata(path).map_or(false, |stat| stat.is_file()) {
                 return Ok(path.clone());
             }
         }
-        if fs::metadata(&jar_path).map_or(false, |stat| stat.is_file()) {
-            self.cached_jar_path = Some(jar_path.clone());
-            return Ok(jar_path);
-        }
+
+        let jar_exists = fs::metadata(&jar_path).map_or(false, |stat| stat.is_file());
 
         set_language_server_installation_status(
             &language_server_id,
             &LanguageServerInstallationStatus::CheckingForUpdate,
         );
 
-        let release = latest_github_release(
-            "nextflow-io/language-server",
-            GithubReleaseOptions {
-                require_assets: true,
-                pre_release: false,
-            },
-        )?;
+        // A pinned tag resolves to that exact release; otherwise track the newest
+        // release in the configured channel (stable by default, pre-releases opt-in).
+        let release = match release_tag {
+            Some(tag) => github_release_by_tag_name("nextflow-io/language-server", &tag),
+            None => latest_github_release(
+                "nextflow-io/language-server",
+                GithubReleaseOptions {
+                    require_assets: true,
+                    pre_release,
+                },
+            ),
+        };
+        let release = match release {
+            Ok(release) => release,
+            // Offline (or the release check otherwise failed): keep whatever jar we
+            // already have so the server still launches. Only surface the error when
+            // there is nothing cached to fall back on.
+            Err(err) => {
+                if jar_exists {
+                    set_language_server_installation_status(
+                        &language_server_id,
+                        &LanguageServerInstallationStatus::None,
+                    );
+                    self.cached_jar_path = Some(jar_path.clone());
+                    return Ok(jar_path);
+                }
+                return Err(err);
+            }
+        };
 
         let asset = release
             .assets
             .iter()
-            .find(|asset| asset.name == "language-server-all.jar")
-            .ok_or_else(|| "No language-server-all.jar asset found".to_string())?;
+            .find(|asset| asset.name == asset_name)
+            .ok_or_else(|| format!("No {asset_name} asset found"))?;
+
+        // The resolved release tag (the asset download URL doubles as a stable
+        // fingerprint when the tag is unchanged) is stored in a sidecar next to the
+        // jar; reuse the cached jar untouched when it already matches.
+        let installed_version = format!("{}\n{}", release.version, asset.download_url);
+        let cached_version = fs::read_to_string(version_path).ok();
+        if jar_exists && cached_version.as_deref() == Some(installed_version.as_str()) {
+            self.cached_jar_path = Some(jar_path.clone());
+            return Ok(jar_path);
+        }
 
         set_language_server_installation_status(
             &language_server_id,
@@ -71,11 +231,41 @@ impl NextflowExtension {
 
         let _ = fs::remove_dir_all(tmp_dir);
 
+        // Record the tag we just installed so the next startup can skip the download.
+        let _ = fs::write(version_path, installed_version);
+
         self.cached_jar_path = Some(jar_path.clone());
         Ok(jar_path)
     }
 }
 
+/// Whether a method completion names a Nextflow channel factory (`Channel.of`) or one
+/// of the DSL2 channel operators, which are rendered with their signature attached.
+fn is_channel_operator(label: &str) -> bool {
+    const OPERATORS: &[&str] = &[
+        "branch", "buffer", "collate", "collect", "collectFile", "combine", "concat",
+        "count", "cross", "distinct", "dump", "filter", "first", "flatMap", "flatten",
+        "groupTuple", "ifEmpty", "join", "last", "map", "max", "merge", "min", "mix",
+        "multiMap", "randomSample", "reduce", "set", "splitCsv", "splitText", "take",
+        "tap", "toList", "toSortedList", "transpose", "unique", "until", "view",
+    ];
+    label.starts_with("Channel.") || OPERATORS.contains(&label)
+}
+
+/// Whether a keyword completion names a Nextflow process directive, which is rendered
+/// with a trailing ` directive` hint. Plain Groovy keywords (`def`, `if`, …) are not.
+fn is_process_directive(label: &str) -> bool {
+    const DIRECTIVES: &[&str] = &[
+        "accelerator", "afterScript", "arch", "array", "beforeScript", "cache",
+        "clusterOptions", "conda", "container", "containerOptions", "cpus", "debug",
+        "disk", "echo", "errorStrategy", "executor", "ext", "fair", "label", "machineType",
+        "maxErrors", "maxForks", "maxRetries", "maxSubmitAwait", "memory", "module",
+        "penv", "pod", "publishDir", "queue", "resourceLabels", "resourceLimits", "scratch",
+        "shell", "spack", "stageInMode", "stageOutMode", "storeDir", "tag", "time",
+    ];
+    DIRECTIVES.contains(&label)
+}
+
 impl Extension for NextflowExtension {
     fn new() -> Self {
         Self {
@@ -86,16 +276,64 @@ impl Extension for NextflowExtension {
     fn language_server_command(
         &mut self,
         language_server_id: &LanguageServerId,
-        _worktree: &Worktree,
+        worktree: &Worktree,
     ) -> zed::Result<zed::Command> {
-        let jar_path = self.language_server_jar_path(language_server_id)?;
+        let settings = LspSettings::for_worktree(SERVER_NAME, worktree).ok();
+        let server_settings = settings.as_ref().and_then(|s| s.settings.as_ref());
+
+        let configured_java = server_settings
+            .and_then(|s| s.get("java_path"))
+            .and_then(|v| v.as_str());
+        let command = self.java_binary(worktree, configured_java);
+
+        // A freshly installed bundled JRE loses the executable bit, so `./bin/java`
+        // would fail with "permission denied" on first run. A `java` found on `$PATH`
+        // or under `$JAVA_HOME` is already executable, so only fix the bundled launcher.
+        if command.starts_with("./") {
+            make_file_executable(&command)?;
+        }
+
+        // Extra JVM flags (e.g. `-Xmx2g`, `-Dfile.encoding=UTF-8`) so large pipelines
+        // don't OOM the language server.
+        let jvm_args: Vec<String> = server_settings
+            .and_then(|s| s.get("jvm_args"))
+            .and_then(|v| v.as_array())
+            .map(|args| {
+                args.iter()
+                    .filter_map(|a| a.as_str().map(|s| s.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let jar_path = self.language_server_jar_path(language_server_id, worktree)?;
+
+        let mut args = jvm_args;
+        args.push("-jar".into());
+        args.push(jar_path);
+
         Ok(zed::Command {
-            command: "./bin/java".into(), // use bundled JRE inside the extension
-            args: vec!["-jar".into(), jar_path],
+            command,
+            args,
             env: Vec::new(),
         })
     }
 
+    fn language_server_initialization_options(
+        &mut self,
+        _language_server_id: &LanguageServerId,
+        worktree: &Worktree,
+    ) -> zed::Result<Option<serde_json::Value>> {
+        Ok(Some(self.server_configuration(worktree)))
+    }
+
+    fn language_server_workspace_configuration(
+        &mut self,
+        _language_server_id: &LanguageServerId,
+        worktree: &Worktree,
+    ) -> zed::Result<Option<serde_json::Value>> {
+        Ok(Some(self.server_configuration(worktree)))
+    }
+
     fn label_for_completion(
         &self,
         _language_server_id: &LanguageServerId,
@@ -112,14 +350,54 @@ impl Extension for NextflowExtension {
                     filter_range: (0..completion.label.len()).into(),
                 })
             }
+            CompletionKind::Keyword if is_process_directive(&completion.label) => {
+                // Process directives such as `cpus`, `memory`, or `publishDir`.
+                let code = completion.label.clone();
+                Some(CodeLabel {
+                    spans: vec![
+                        CodeLabelSpan::code_range(0..code.len()),
+                        CodeLabelSpan::literal(" directive".to_string(), None),
+                    ],
+                    code,
+                    filter_range: (0..completion.label.len()).into(),
+                })
+            }
             CompletionKind::Method => {
                 let code = format!("{}()", completion.label);
+                // Channel factories (`Channel.of`) and operators (`.map`, `.collect`)
+                // read better with their signature trailing the call.
+                if is_channel_operator(&completion.label) {
+                    if let Some(detail) = &completion.detail {
+                        return Some(CodeLabel {
+                            spans: vec![
+                                CodeLabelSpan::code_range(0..code.len()),
+                                CodeLabelSpan::literal(format!(" {detail}"), None),
+                            ],
+                            code,
+                            filter_range: (0..completion.label.len()).into(),
+                        });
+                    }
+                }
                 Some(CodeLabel {
                     spans: vec![CodeLabelSpan::code_range(0..code.len())],
                     code,
                     filter_range: (0..completion.label.len()).into(),
                 })
             }
+            CompletionKind::Snippet => {
+                // Scaffold snippets (e.g. `process`/`workflow` blocks) show their full
+                // expansion as the syntax-highlighted code span.
+                let code = completion.detail.clone().unwrap_or_else(|| completion.label.clone());
+                // The expansion isn't guaranteed to start with the label, so anchor the
+                // filter range to where the label occurs in `code` and keep it in bounds.
+                let filter_start = code.find(&completion.label).unwrap_or(0);
+                let filter_end = (filter_start + completion.label.len()).min(code.len());
+                Some(CodeLabel {
+                    spans: vec![CodeLabelSpan::code_range(0..code.len())],
+                    code,
+                    filter_range: (filter_start..filter_end).into(),
+                })
+            }
             CompletionKind::Variable => {
                 let def = "def ";
                 let code = format!("{def}{}", completion.label);